@@ -0,0 +1,874 @@
+//! Proc-macro implementation crate for [`coerce_pattern`]. A crate with
+//! `proc-macro = true` cannot export any other public item (such as the
+//! `PatternMismatch` error type), so the macros live here and the
+//! `coerce_pattern` crate re-exports them alongside its regular items.
+//! Depend on `coerce_pattern` directly rather than on this crate.
+//!
+//! [`coerce_pattern`]: https://docs.rs/coerce_pattern
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{quote, ToTokens};
+use syn::{
+    parse::{Parse, ParseStream},
+    parse_macro_input, Token,
+};
+
+/// Full input of the `coerce_pattern!` macro
+struct CoercePatternInput {
+    /// expression that should be coerced into a pattern
+    expression: Expression,
+    /// pattern that expression should be coerced into
+    target: Target,
+    /// expression (that is valid given target pattern
+    /// and surrounding context) that should be returned, or,
+    /// in the arity-2 form, the auto-generated tuple of bindings
+    result: CoerceResult,
+}
+
+impl Parse for CoercePatternInput {
+    /// Parses the input of coerce_pattern! by separating it into
+    /// expression and target, then, if a third comma-separated piece
+    /// is present, an explicit result expression. If it is absent (the
+    /// arity-2 form), the result is instead every binding introduced by
+    /// the target pattern, collected in left-to-right order.
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let expression = input.parse()?;
+        input.parse::<Token![,]>()?;
+        let target: Target = input.parse()?;
+        let result = if input.peek(Token![,]) {
+            input.parse::<Token![,]>()?;
+            CoerceResult::Explicit(input.parse()?)
+        } else {
+            CoerceResult::AutoBindings(collect_bindings(&target.pat))
+        };
+        Ok(Self {
+            expression,
+            target,
+            result,
+        })
+    }
+}
+
+impl ToTokens for CoercePatternInput {
+    /// Performs code-generation for the coerce_pattern! macro. Uses a
+    /// match with one arm with target pattern and one wildcard arm.
+    fn to_tokens(&self, tokens: &mut TokenStream2) {
+        let Self {
+            expression,
+            target,
+            result,
+        } = self;
+        tokens.extend(quote! {
+            match #expression {
+                #target => { #result }
+                _ => panic!("expression didn't match target pattern in coerce_pattern")
+            }
+        });
+    }
+}
+
+/// Full input of the `assert_pattern!` macro
+struct AssertPatternInput {
+    /// expression that should match the pattern
+    expression: Expression,
+    /// pattern that the expression should match
+    target: Target,
+}
+
+impl Parse for AssertPatternInput {
+    /// Parses the input of the assert_pattern! macro by parsing
+    /// the expression and target pattern in that order.
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let expression = input.parse()?;
+        input.parse::<Token![,]>()?;
+        let target = input.parse()?;
+        Ok(Self { expression, target })
+    }
+}
+
+impl ToTokens for AssertPatternInput {
+    /// Performs code-generation for the assert_pattern! macro. Uses a
+    /// match with one arm with target pattern and one wildcard arm.
+    fn to_tokens(&self, tokens: &mut TokenStream2) {
+        let Self { expression, target } = self;
+        tokens.extend(quote! {
+            match #expression {
+                #target => {}
+                _ => panic!("expression didn't match target pattern in assert_pattern")
+            }
+        });
+    }
+}
+
+/// The target pattern can be any pattern (including refutable patterns),
+/// optionally followed by an `if <expr>` match guard.
+struct Target {
+    /// the pattern itself
+    pat: syn::Pat,
+    /// optional guard expression that, if present, is appended to the
+    /// pattern with `if` to form a full match-arm guard
+    guard: Option<syn::Expr>,
+}
+
+impl Parse for Target {
+    /// Parses the Target pattern using syn::Pat::parse_multi because
+    /// it can accept any pattern that can label a match arm, followed
+    /// by an optional `if <expr>` guard clause
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let pat = syn::Pat::parse_multi(input)?;
+        let guard = if input.peek(Token![if]) {
+            input.parse::<Token![if]>()?;
+            Some(input.parse()?)
+        } else {
+            None
+        };
+        Ok(Self { pat, guard })
+    }
+}
+
+impl ToTokens for Target {
+    /// Code-generation of a target is the underlying syn::Pat, followed
+    /// by `if #guard` when a guard is present, so that `#target` can be
+    /// used directly as a match-arm label in surrounding `quote!` calls
+    fn to_tokens(&self, tokens: &mut TokenStream2) {
+        let Self { pat, guard } = self;
+        pat.to_tokens(tokens);
+        if let Some(guard) = guard {
+            tokens.extend(quote! { if #guard });
+        }
+    }
+}
+
+impl Target {
+    /// Returns `if <guard>` when a guard is present, or nothing
+    /// otherwise. Used where the pattern itself must be embedded inside
+    /// a larger pattern (e.g. wrapped in `Some(..)`), so the guard can't
+    /// simply trail the pattern the way `ToTokens` does for a top-level
+    /// match-arm label.
+    fn guard_clause(&self) -> TokenStream2 {
+        match &self.guard {
+            Some(guard) => quote! { if #guard },
+            None => TokenStream2::new(),
+        }
+    }
+}
+
+/// The result of a `coerce_pattern!` invocation: either the explicit
+/// result expression of the arity-3 form, or, in the arity-2 form, every
+/// variable bound by the target pattern collected into a tuple.
+enum CoerceResult {
+    /// explicit result expression, as in the arity-3 form
+    Explicit(Expression),
+    /// idents bound by the target pattern, in left-to-right order, used
+    /// to auto-generate a result in the arity-2 form
+    AutoBindings(Vec<syn::Ident>),
+}
+
+impl ToTokens for CoerceResult {
+    /// Code-generation of an explicit result is the same as the
+    /// underlying expression; an auto-generated result becomes the bare
+    /// ident when there is exactly one binding, `()` when there are
+    /// none, and a tuple of idents otherwise.
+    fn to_tokens(&self, tokens: &mut TokenStream2) {
+        match self {
+            Self::Explicit(expression) => expression.to_tokens(tokens),
+            Self::AutoBindings(idents) => match idents.as_slice() {
+                [] => tokens.extend(quote! { () }),
+                [ident] => tokens.extend(quote! { #ident }),
+                idents => tokens.extend(quote! { (#(#idents),*) }),
+            },
+        }
+    }
+}
+
+/// Recursively collects the idents of every variable bound by `pat`, in
+/// left-to-right traversal order, skipping wildcards and literals. Used
+/// to auto-generate the result of the arity-2 form of `coerce_pattern!`.
+/// Every `Pat::Ident` counts as a binding, including a bare unit-variant
+/// or constant name such as `None`, since names aren't resolved here.
+fn collect_bindings(pat: &syn::Pat) -> Vec<syn::Ident> {
+    let mut idents = Vec::new();
+    collect_bindings_into(pat, &mut idents);
+    idents
+}
+
+/// Helper for [`collect_bindings`] that accumulates into `idents` instead
+/// of allocating a fresh `Vec` at every level of recursion.
+fn collect_bindings_into(pat: &syn::Pat, idents: &mut Vec<syn::Ident>) {
+    match pat {
+        syn::Pat::Ident(pat_ident) => {
+            idents.push(pat_ident.ident.clone());
+            if let Some((_, subpat)) = &pat_ident.subpat {
+                collect_bindings_into(subpat, idents);
+            }
+        }
+        syn::Pat::TupleStruct(pat_tuple_struct) => {
+            for elem in &pat_tuple_struct.elems {
+                collect_bindings_into(elem, idents);
+            }
+        }
+        syn::Pat::Struct(pat_struct) => {
+            for field in &pat_struct.fields {
+                collect_bindings_into(&field.pat, idents);
+            }
+        }
+        syn::Pat::Tuple(pat_tuple) => {
+            for elem in &pat_tuple.elems {
+                collect_bindings_into(elem, idents);
+            }
+        }
+        syn::Pat::Slice(pat_slice) => {
+            for elem in &pat_slice.elems {
+                collect_bindings_into(elem, idents);
+            }
+        }
+        syn::Pat::Reference(pat_reference) => {
+            collect_bindings_into(&pat_reference.pat, idents);
+        }
+        syn::Pat::Paren(pat_paren) => {
+            collect_bindings_into(&pat_paren.pat, idents);
+        }
+        syn::Pat::Or(pat_or) => {
+            // every alternative must bind the same names (enforced by
+            // the compiler), so only the first needs to be walked
+            if let Some(first) = pat_or.cases.first() {
+                collect_bindings_into(first, idents);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Expression is a thin wrapper around syn::Expr
+struct Expression(syn::Expr);
+
+impl Parse for Expression {
+    /// Parses input in the same way as the underlying syn::Expr
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        syn::Expr::parse(input).map(Self)
+    }
+}
+
+impl ToTokens for Expression {
+    /// Code-generation of an expression is the same as the underlying syn::Expr
+    fn to_tokens(&self, tokens: &mut TokenStream2) {
+        self.0.to_tokens(tokens);
+    }
+}
+
+/// Asserts that an expression matches a pattern, like a
+/// generalized `assert!(result.is_ok())`.
+///
+/// `assert_pattern!($e, $p)` expands roughly to
+/// ```text
+/// match $e {
+///     $p => {}
+///     _ => panic!()
+/// }
+/// ```
+///
+/// # Motivation
+///
+/// One of the main motivations of writing this library,and
+/// `assert_pattern!` in particular, is to make tests more concise.
+/// There are many cases where a function should return an object
+/// matching a specifically general pattern, but, as this pattern becomes
+/// more complicated, so does the unit test, but this doesn't need to be
+/// the case. Compare the following two blocks of code. First, the
+/// version without using `assert_pattern!`
+/// ```rust
+/// # fn rand_bool() -> bool {true}
+/// struct S {
+///     x: u32,
+///     y: u32,
+/// }
+/// fn one_of_two_s_forms(which: bool) -> S {
+///     if which {
+///         S {x: 1, y: 2}
+///     } else {
+///         S {x: 3, y: 4}
+///     }
+/// }
+/// let s = one_of_two_s_forms(rand_bool());
+/// assert!(((s.x == 1) && (s.y == 2)) || ((s.x == 3) && (s.y == 4)));
+/// ```
+/// Next, the same code using `assert_pattern!`
+/// ```rust
+/// # use coerce_pattern::assert_pattern;
+/// # fn rand_bool() -> bool {true}
+/// struct S {
+///     x: u32,
+///     y: u32,
+/// }
+/// fn one_of_two_s_forms(which: bool) -> S {
+///     if which {
+///         S {x: 1, y: 2}
+///     } else {
+///         S {x: 3, y: 4}
+///     }
+/// }
+/// assert_pattern!(one_of_two_s_forms(rand_bool()), S{x: 1, y: 2} | S{x: 3, y: 4});
+/// ```
+///
+///
+/// # Option example
+///
+/// One way of using `assert_pattern!` is to destructure an object
+/// (like a tuple here) inside an Option when you would otherwise use
+/// `unwrap` and a match statement, e.g.
+/// ```rust
+/// # use coerce_pattern::assert_pattern;
+/// let o = Some((1, "this string could change and this code still wouldn't panic"));
+/// assert_pattern!(o, Some((1, _)));
+/// ```
+/// This code is roughly equivalent to
+/// ```rust
+/// let o = Some((1, "this string could change and this code still wouldn't panic"));
+/// assert!(
+///     match o {
+///         Some((1, _)) => true,
+///         _ => false,
+///     }
+/// )
+/// ```
+///
+/// # Custom type example
+///
+/// More useful examples arise naturally in cases involving custom types
+/// ```rust
+/// # use coerce_pattern::assert_pattern;
+/// enum MyEnum {
+///     A(u32),
+///     B(i64),
+/// }
+/// let e = MyEnum::B(-1);
+/// assert_pattern!(e, MyEnum::B(_));
+/// ```
+/// This code will panic if `e` is set to a `MyEnum::A`. If it doesn't panic,
+/// though, then `x` is bound to the i64 in the `MyEnum::B` instance.
+///
+/// # Guard example
+///
+/// A pattern may be followed by an `if` guard, just like a match arm,
+/// letting the assertion reference bindings introduced by the pattern.
+/// ```rust
+/// # use coerce_pattern::assert_pattern;
+/// let o = Some(6);
+/// assert_pattern!(o, Some(n) if n > 5);
+/// ```
+/// This panics both when `o` isn't `Some` and when its inner value isn't
+/// greater than `5`.
+#[proc_macro]
+pub fn assert_pattern(
+    input: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+    let i = parse_macro_input!(input as AssertPatternInput);
+    quote! { #i }.into()
+}
+
+/// Coerces an expression into a pattern, like a generalized unwrap.
+///
+/// `coerce_pattern!($e, $t, $r)` expands roughly to
+/// ```text
+/// match $e {
+///     $t => $r,
+///     _ => panic!()
+/// }
+/// ```
+///
+/// Note that, up to differences in panic messages, `assert_pattern!($e, $t)` is
+/// equivalent to `coerce_pattern!($e, $t, {})`.
+///
+/// # Motivation
+///
+/// One of the main uses of this macro is to better measure test coverage in
+/// library code which contains panics. For example, consider the following
+/// code snippet:
+/// ```rust
+/// enum MyEnum {
+///     A { x: u32 },
+///     B(u64, u64),
+/// }
+/// impl MyEnum {
+///     fn new_b(first: u64) -> Self {
+///         Self::B(first, 0)
+///     }
+/// }
+/// let o = MyEnum::new_b(763); // guaranteed to be a MyEnum::B
+/// let x = match o {
+///     MyEnum::B(_, x) => x,
+///     MyEnum::A{..} => panic!("this panic will never be tested or testable"),
+/// };
+/// assert_eq!(x, 0);
+/// ```
+/// This code will lead to a line of untested code, no matter how thoroughly it is tested.
+/// Compare this to the same code using `coerce_pattern!`
+/// ```rust
+/// # use coerce_pattern::coerce_pattern;
+/// enum MyEnum {
+///     A { x: u32 },
+///     B(u64, u64),
+/// }
+/// impl MyEnum {
+///     fn new_b(first: u64) -> Self {
+///         Self::B(first, 0)
+///     }
+/// }
+/// let o = MyEnum::new_b(763); // guaranteed to be a MyEnum::B
+/// let x = coerce_pattern!(o, MyEnum::B(_, x), x);
+/// assert_eq!(x, 0);
+/// ```
+/// In contrast to the code using `match ... { ... panic!()}`,
+/// this code has no lines or regions that aren't tested.
+///
+/// # Option example
+///
+/// A trivial example (probably better replaced by `Option::unwrap()`)
+/// that unwraps an option while also performing an expression.
+/// ```rust
+/// # use coerce_pattern::coerce_pattern;
+/// let o = Some(1);
+/// let x = coerce_pattern!(o, Some(y), y + 2);
+/// assert_eq!(x, 3);
+/// ```
+/// Note that this is probably better replaced with `let x = o.unwrap() + 2;`
+/// The only difference between the two representations is the panic message.
+///
+/// # Custom type example
+///
+/// More useful examples arise naturally in cases involving custom types
+/// ```rust
+/// # use coerce_pattern::coerce_pattern;
+/// enum MyEnum {
+///     A(u32),
+///     B(i64),
+/// }
+/// let e = MyEnum::B(-1);
+/// let x = coerce_pattern!(e, MyEnum::B(y), y);
+/// assert_eq!(x, -1);
+/// ```
+/// This code will panic if `e` is set to a `MyEnum::A`. If it doesn't panic,
+/// though, then `x` is bound to the i64 in the `MyEnum::B` instance.
+///
+/// # Guard example
+///
+/// A pattern may be followed by an `if` guard, just like a match arm,
+/// letting the result expression rely on a condition already checked
+/// against bindings introduced by the pattern.
+/// ```rust
+/// # use coerce_pattern::coerce_pattern;
+/// let o = Some(6);
+/// let x = coerce_pattern!(o, Some(n) if n > 5, n * 2);
+/// assert_eq!(x, 12);
+/// ```
+/// This panics both when `o` isn't `Some` and when its inner value isn't
+/// greater than `5`.
+///
+/// # Arity-2 form
+///
+/// When the result expression is omitted, `coerce_pattern!($e, $t)`
+/// evaluates instead to every variable bound by `$t`, in left-to-right
+/// order: a single binding yields the bare value, multiple bindings
+/// yield a tuple, and no bindings yield `()`.
+/// ```rust
+/// # use coerce_pattern::coerce_pattern;
+/// enum MyEnum {
+///     A(u32),
+///     B(i64, i64),
+/// }
+/// let e = MyEnum::B(-1, 2);
+/// let (x, y) = coerce_pattern!(e, MyEnum::B(x, y));
+/// assert_eq!((x, y), (-1, 2));
+/// ```
+/// This is equivalent to `coerce_pattern!(e, MyEnum::B(x, y), (x, y))`.
+///
+/// Bindings are found syntactically: every bare identifier in the pattern
+/// is collected, so a unit variant or constant written as a bare name
+/// (e.g. `None`) is included in the result as if it were a binding.
+#[proc_macro]
+pub fn coerce_pattern(
+    input: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+    let i = parse_macro_input!(input as CoercePatternInput);
+    quote! { #i }.into()
+}
+
+/// Full input of the `coerce_first!` macro
+struct CoerceFirstInput {
+    /// expression yielding an `IntoIterator` to search
+    iterable: Expression,
+    /// pattern that the first matching element should match
+    target: Target,
+    /// expression (that is valid given target pattern
+    /// and surrounding context) that should be returned
+    result: Expression,
+}
+
+impl Parse for CoerceFirstInput {
+    /// Parses the input of coerce_first! by separating it
+    /// into iterable, target, and result in that order
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let iterable = input.parse()?;
+        input.parse::<Token![,]>()?;
+        let target = input.parse()?;
+        input.parse::<Token![,]>()?;
+        let result = input.parse()?;
+        Ok(Self {
+            iterable,
+            target,
+            result,
+        })
+    }
+}
+
+impl ToTokens for CoerceFirstInput {
+    /// Performs code-generation for the coerce_first! macro. Iterates
+    /// the given `IntoIterator`, breaking out of the loop with the
+    /// result as soon as an element matches the target pattern, and
+    /// panicking once the iterator is exhausted without a match.
+    fn to_tokens(&self, tokens: &mut TokenStream2) {
+        let Self {
+            iterable,
+            target,
+            result,
+        } = self;
+        let pat = &target.pat;
+        let guard_clause = target.guard_clause();
+        tokens.extend(quote! {
+            {
+                let mut __coerce_first_iter = IntoIterator::into_iter(#iterable);
+                loop {
+                    match __coerce_first_iter.next() {
+                        Some(#pat) #guard_clause => break { #result },
+                        Some(_) => continue,
+                        None => panic!("no element matched target pattern in coerce_first"),
+                    }
+                }
+            }
+        });
+    }
+}
+
+/// Searches an iterable for the first element matching a pattern and
+/// coerces it, like `coerce_pattern!` but scanning a collection instead
+/// of a single expression.
+///
+/// `coerce_first!($iterable, $t, $r)` expands roughly to
+/// ```text
+/// {
+///     let mut it = IntoIterator::into_iter($iterable);
+///     loop {
+///         match it.next() {
+///             Some($t) => break $r,
+///             Some(_) => continue,
+///             None => panic!(),
+///         }
+///     }
+/// }
+/// ```
+///
+/// # Motivation
+///
+/// Tests often assert that a `Vec` (or other collection) returned by a
+/// function contains an element of a certain shape, without caring about
+/// its position or about the other elements. Without `coerce_first!`,
+/// this requires a manual loop or an `.iter().find(...)` followed by an
+/// `unwrap()` and a second match to destructure the found element.
+/// `coerce_first!` combines the search and the destructuring into one
+/// expression, in the same panic-on-mismatch style as `coerce_pattern!`.
+///
+/// # Example
+/// ```rust
+/// # use coerce_pattern::coerce_first;
+/// enum MyEnum {
+///     A(u32),
+///     B(i64),
+/// }
+/// let v = vec![MyEnum::A(1), MyEnum::B(-2), MyEnum::B(3)];
+/// let x = coerce_first!(&v, MyEnum::B(x), *x);
+/// assert_eq!(x, -2);
+/// ```
+/// This panics if no element of `v` is a `MyEnum::B`. Guards and
+/// or-patterns work here exactly as they do in `coerce_pattern!`, e.g.
+/// `coerce_first!(&v, MyEnum::B(x) if *x > 0, *x)` would instead find
+/// the `3`.
+#[proc_macro]
+pub fn coerce_first(
+    input: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+    let i = parse_macro_input!(input as CoerceFirstInput);
+    quote! { #i }.into()
+}
+
+/// Full input of the `replace_pattern!` macro
+struct ReplacePatternInput {
+    /// expression yielding a mutable `IntoIterator` to walk
+    collection: Expression,
+    /// pattern that an element should match to be replaced
+    target: Target,
+    /// expression (that may reference the target pattern's bindings)
+    /// that each matching element is overwritten with
+    replacement: Expression,
+}
+
+impl Parse for ReplacePatternInput {
+    /// Parses the input of replace_pattern! by separating it
+    /// into collection, target, and replacement in that order
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let collection = input.parse()?;
+        input.parse::<Token![,]>()?;
+        let target = input.parse()?;
+        input.parse::<Token![,]>()?;
+        let replacement = input.parse()?;
+        Ok(Self {
+            collection,
+            target,
+            replacement,
+        })
+    }
+}
+
+impl ToTokens for ReplacePatternInput {
+    /// Performs code-generation for the replace_pattern! macro. Walks
+    /// the given mutable `IntoIterator`, and for every element matching
+    /// the target pattern, rebinds the pattern's variables (via match
+    /// ergonomics, since each element is visited by mutable reference)
+    /// and overwrites the element with the replacement, counting how
+    /// many elements were replaced.
+    fn to_tokens(&self, tokens: &mut TokenStream2) {
+        let Self {
+            collection,
+            target,
+            replacement,
+        } = self;
+        tokens.extend(quote! {
+            {
+                let mut __replace_pattern_count = 0usize;
+                for __replace_pattern_slot in IntoIterator::into_iter(#collection) {
+                    match __replace_pattern_slot {
+                        #target => {
+                            *__replace_pattern_slot = #replacement;
+                            __replace_pattern_count += 1;
+                        }
+                        _ => {}
+                    }
+                }
+                __replace_pattern_count
+            }
+        });
+    }
+}
+
+/// Performs in-place structural search-and-replace over a mutable
+/// collection: every element matching a pattern is overwritten using its
+/// own destructured contents, and the number of replacements is returned.
+///
+/// `replace_pattern!($c, $t, $r)` expands roughly to
+/// ```text
+/// {
+///     let mut n = 0usize;
+///     for slot in IntoIterator::into_iter($c) {
+///         match slot {
+///             $t => { *slot = $r; n += 1; }
+///             _ => {}
+///         }
+///     }
+///     n
+/// }
+/// ```
+///
+/// # Motivation
+///
+/// `coerce_first!` locates and destructures one matching element; this
+/// macro adapts the same idea to transforming every matching element of
+/// a collection in place, without writing a manual `for` loop and `if
+/// let` at every call site. The replacement expression may reference the
+/// bindings introduced by the pattern, since it is evaluated while those
+/// bindings are still in scope.
+///
+/// A key invariant is that the replacement expression must be
+/// type-compatible with the element type, and that its bindings are
+/// moved or borrowed from the matched slot (since each slot is visited
+/// by mutable reference), so a binding like `x` in `MyEnum::B(x)` refers
+/// to the same memory that `*slot = $r` subsequently overwrites.
+///
+/// # Example
+/// ```rust
+/// # use coerce_pattern::replace_pattern;
+/// enum MyEnum {
+///     A(u32),
+///     B(i64),
+/// }
+/// let mut v = vec![MyEnum::A(1), MyEnum::B(-2), MyEnum::B(3)];
+/// let n = replace_pattern!(&mut v, MyEnum::B(x) if *x < 0, MyEnum::B(-*x));
+/// assert_eq!(n, 1);
+/// ```
+/// Here only the `MyEnum::B(-2)` element is negative, so it alone is
+/// replaced with `MyEnum::B(2)`, and `replace_pattern!` returns `1`.
+#[proc_macro]
+pub fn replace_pattern(
+    input: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+    let i = parse_macro_input!(input as ReplacePatternInput);
+    quote! { #i }.into()
+}
+
+/// Full input of the `try_coerce_pattern!` macro
+struct TryCoercePatternInput {
+    /// expression that should be coerced into a pattern
+    expression: Expression,
+    /// pattern that expression should be coerced into
+    target: Target,
+    /// expression (that is valid given target pattern
+    /// and surrounding context) that should be returned
+    result: Expression,
+}
+
+impl Parse for TryCoercePatternInput {
+    /// Parses the input of try_coerce_pattern! by separating it
+    /// into expression, target, and result in that order
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let expression = input.parse()?;
+        input.parse::<Token![,]>()?;
+        let target = input.parse()?;
+        input.parse::<Token![,]>()?;
+        let result = input.parse()?;
+        Ok(Self {
+            expression,
+            target,
+            result,
+        })
+    }
+}
+
+impl ToTokens for TryCoercePatternInput {
+    /// Performs code-generation for the try_coerce_pattern! macro. Uses
+    /// a match with one arm with target pattern and one wildcard arm,
+    /// wrapping the two outcomes in `Ok`/`Err` instead of panicking.
+    fn to_tokens(&self, tokens: &mut TokenStream2) {
+        let Self {
+            expression,
+            target,
+            result,
+        } = self;
+        let pattern_string = quote! { #target }.to_string();
+        tokens.extend(quote! {
+            match #expression {
+                #target => Ok(#result),
+                _ => Err(::coerce_pattern::PatternMismatch { pattern: #pattern_string.to_string() }),
+            }
+        });
+    }
+}
+
+/// Full input of the `try_assert_pattern!` macro
+struct TryAssertPatternInput {
+    /// expression that should match the pattern
+    expression: Expression,
+    /// pattern that the expression should match
+    target: Target,
+}
+
+impl Parse for TryAssertPatternInput {
+    /// Parses the input of the try_assert_pattern! macro by parsing
+    /// the expression and target pattern in that order.
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let expression = input.parse()?;
+        input.parse::<Token![,]>()?;
+        let target = input.parse()?;
+        Ok(Self { expression, target })
+    }
+}
+
+impl ToTokens for TryAssertPatternInput {
+    /// Performs code-generation for the try_assert_pattern! macro. Uses
+    /// a match with one arm with target pattern and one wildcard arm,
+    /// wrapping the two outcomes in `Ok`/`Err` instead of panicking.
+    fn to_tokens(&self, tokens: &mut TokenStream2) {
+        let Self { expression, target } = self;
+        let pattern_string = quote! { #target }.to_string();
+        tokens.extend(quote! {
+            match #expression {
+                #target => Ok(()),
+                _ => Err(::coerce_pattern::PatternMismatch { pattern: #pattern_string.to_string() }),
+            }
+        });
+    }
+}
+
+/// Fallible counterpart to [`coerce_pattern!`](crate::coerce_pattern), for
+/// non-test code paths where aborting on a mismatch is unacceptable.
+///
+/// `try_coerce_pattern!($e, $t, $r)` expands roughly to
+/// ```text
+/// match $e {
+///     $t => Ok($r),
+///     _ => Err(PatternMismatch { .. }),
+/// }
+/// ```
+///
+/// # Motivation
+///
+/// `coerce_pattern!` is well suited to tests and other code paths where
+/// a mismatch truly is a bug, but library code that merely expects one
+/// of several shapes (e.g. a response parsed from untrusted input)
+/// usually needs to recover from a mismatch rather than panic.
+/// `try_coerce_pattern!` gives the same destructuring ergonomics in a
+/// `?`-friendly form that returns a [`PatternMismatch`] error instead.
+///
+/// # Example
+/// ```rust
+/// # use coerce_pattern::try_coerce_pattern;
+/// enum MyEnum {
+///     A(u32),
+///     B(i64),
+/// }
+/// fn get_b(e: MyEnum) -> Result<i64, coerce_pattern::PatternMismatch> {
+///     try_coerce_pattern!(e, MyEnum::B(y), y)
+/// }
+/// assert_eq!(get_b(MyEnum::B(-1)), Ok(-1));
+/// assert!(get_b(MyEnum::A(1)).is_err());
+/// ```
+#[proc_macro]
+pub fn try_coerce_pattern(
+    input: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+    let i = parse_macro_input!(input as TryCoercePatternInput);
+    quote! { #i }.into()
+}
+
+/// Fallible counterpart to [`assert_pattern!`](crate::assert_pattern), for
+/// non-test code paths where aborting on a mismatch is unacceptable.
+///
+/// `try_assert_pattern!($e, $p)` expands roughly to
+/// ```text
+/// match $e {
+///     $p => Ok(()),
+///     _ => Err(PatternMismatch { .. }),
+/// }
+/// ```
+///
+/// Composes naturally with `?` in a function returning
+/// `Result<_, PatternMismatch>` (or any error type `PatternMismatch`
+/// converts into).
+///
+/// # Example
+/// ```rust
+/// # use coerce_pattern::try_assert_pattern;
+/// enum MyEnum {
+///     A(u32),
+///     B(i64),
+/// }
+/// fn check_b(e: &MyEnum) -> Result<(), coerce_pattern::PatternMismatch> {
+///     try_assert_pattern!(e, MyEnum::B(_))?;
+///     Ok(())
+/// }
+/// assert!(check_b(&MyEnum::B(-1)).is_ok());
+/// assert!(check_b(&MyEnum::A(1)).is_err());
+/// ```
+#[proc_macro]
+pub fn try_assert_pattern(
+    input: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+    let i = parse_macro_input!(input as TryAssertPatternInput);
+    quote! { #i }.into()
+}